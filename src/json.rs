@@ -0,0 +1,201 @@
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+// Minimal JSON reader used for loading saved webs.
+//
+// Only the subset of JSON written by MorassWeb::save_to_json is needed: objects, arrays, numbers
+// and strings. Numbers are kept as their source text so that integers and floats can each be
+// parsed losslessly into the type the caller expects.
+pub enum Value {
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+// Writes a float for save_to_json. JSON has no infinity or NaN, so those are written as the
+// strings "inf", "-inf" and "NaN", which Value::as_f64 reads back.
+pub struct Float(pub f64);
+
+impl fmt::Display for Float {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_finite() {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "\"{}\"", self.0)
+        }
+    }
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> std::io::Result<&Value> {
+        match self {
+            Value::Object(fields) => fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v)
+                .ok_or_else(|| invalid(format!("missing field '{}'", key))),
+            _ => Err(invalid(format!("expected an object containing '{}'", key))),
+        }
+    }
+
+    pub fn as_object(&self) -> std::io::Result<&Vec<(String, Value)>> {
+        match self {
+            Value::Object(fields) => Ok(fields),
+            _ => Err(invalid("expected an object".to_string())),
+        }
+    }
+
+    pub fn as_array(&self) -> std::io::Result<&Vec<Value>> {
+        match self {
+            Value::Array(items) => Ok(items),
+            _ => Err(invalid("expected an array".to_string())),
+        }
+    }
+
+    pub fn as_f64(&self) -> std::io::Result<f64> {
+        match self {
+            Value::Number(n) => n.parse().map_err(|_| invalid(format!("invalid float '{}'", n))),
+            Value::String(s) => match s.as_str() {
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                "NaN" => Ok(f64::NAN),
+                _ => Err(invalid(format!("invalid float '{}'", s))),
+            },
+            _ => Err(invalid("expected a number".to_string())),
+        }
+    }
+
+    pub fn as_usize(&self) -> std::io::Result<usize> {
+        match self {
+            Value::Number(n) => n.parse().map_err(|_| invalid(format!("invalid integer '{}'", n))),
+            _ => Err(invalid("expected a number".to_string())),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> std::io::Result<Value> {
+    let mut parser = Parser { bytes: input.as_bytes(), pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(invalid(format!("trailing characters at byte {}", parser.pos)));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> std::io::Result<u8> {
+        self.skip_whitespace();
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| invalid("unexpected end of input".to_string()))
+    }
+
+    fn expect(&mut self, byte: u8) -> std::io::Result<()> {
+        if self.peek()? != byte {
+            return Err(invalid(format!("expected '{}' at byte {}", byte as char, self.pos)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> std::io::Result<Value> {
+        match self.peek()? {
+            b'{' => self.object(),
+            b'[' => self.array(),
+            b'"' => Ok(Value::String(self.string()?)),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> std::io::Result<Value> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                _ => return Err(invalid(format!("expected ',' or '}}' at byte {}", self.pos))),
+            }
+        }
+    }
+
+    fn array(&mut self) -> std::io::Result<Value> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(invalid(format!("expected ',' or ']' at byte {}", self.pos))),
+            }
+        }
+    }
+
+    // Object keys and non-finite floats are the only strings written, and they never need escaping
+    fn string(&mut self) -> std::io::Result<String> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'"' {
+            if self.bytes[self.pos] == b'\\' {
+                return Err(invalid(format!("unsupported escape at byte {}", self.pos)));
+            }
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.pos])
+            .map_err(|_| invalid("invalid utf-8 in string".to_string()))?
+            .to_string();
+        self.expect(b'"')?;
+        Ok(text)
+    }
+
+    fn number(&mut self) -> std::io::Result<Value> {
+        let start = self.pos;
+        while self.pos < self.bytes.len()
+            && matches!(self.bytes[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(invalid(format!("unexpected character at byte {}", self.pos)));
+        }
+        // Only ASCII bytes were consumed above
+        Ok(Value::Number(String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned()))
+    }
+}
@@ -0,0 +1,2 @@
+mod json;
+pub mod web;
@@ -1,11 +1,15 @@
-use rand::random;
+use crate::json;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Error, ErrorKind};
 use std::hash::Hash;
-use std::ops::{Add, AddAssign, DerefMut};
+use std::ops::{Add, AddAssign};
 use rayon::prelude::*;
-use std::sync::{Arc, RwLock};
-use rand::distributions::uniform::SampleBorrow;
+use std::sync::{Arc, Mutex, RwLock};
 
 // Prototype neural network that focuses on facilitating an all-node-input-all-node-output network. The idea is that all neurons in the brain are interconnected and are used as both input and output simultaneously.
 //
@@ -25,6 +29,8 @@ use rand::distributions::uniform::SampleBorrow;
 // Cf: Charge consumption fixed; when the threshold is triggered, this fixed value is subtracted from the current charge (before Cp)
 // Dp: Decay percentage; this is how much the current charge decays every step current_chg*=(1-Dp)
 // Df: Decay fixed; this value is subtracted from the current charge every step
+// R: Resting potential; decay never takes the charge below this value (0.0 by default), though firing can
+// A: Activation; how the start node's charge is turned into the pulse sent along each outgoing edge (Hard by default)
 #[derive(PartialEq)]
 pub struct Node {
     id: usize,
@@ -37,6 +43,55 @@ pub struct Node {
     charge_consumption_fixed: f64,
     decay_percentage: f64,
     decay_fixed: f64,
+    resting_potential: f64,
+    activation: Activation,
+}
+
+// Activation functions turning a node's charge into the pulse sent along an edge
+//
+// Hard: charge * Op + Of once the charge meets the threshold, nothing below it
+// Sigmoid: the Hard output scaled by a logistic curve centred on the threshold, so charge just below the
+//          threshold still leaks a fraction through; a higher steepness approaches Hard.
+//          Only charge at or above the threshold counts as the edge firing. Below it the leak is added to
+//          the end node without resetting last_fire, counting an operation or applying Hebbian learning,
+//          and like any pulse it is not taken from the start node's charge
+// ReLU: (charge - threshold) * Op + Of once the charge meets the threshold, so only the excess charge is passed on
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Activation {
+    #[default]
+    Hard,
+    Sigmoid { steepness: f64 },
+    ReLU,
+}
+
+impl Activation {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Activation::Sigmoid { steepness } = self {
+            if !steepness.is_finite() || *steepness <= 0.0 {
+                return Err(format!("sigmoid steepness must be finite and greater than 0, got {}", steepness));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn activation_output(charge: f64, threshold: f64, activation: Activation, out_percentage: f64, out_fixed: f64) -> f64 {
+    match activation {
+        Activation::Hard => if charge >= threshold {
+            charge * out_percentage + out_fixed
+        } else {
+            0.0
+        },
+        Activation::Sigmoid { steepness } => {
+            let scale = 1.0 / (1.0 + (-steepness * (charge - threshold)).exp());
+            (charge * out_percentage + out_fixed) * scale
+        }
+        Activation::ReLU => if charge >= threshold {
+            (charge - threshold) * out_percentage + out_fixed
+        } else {
+            0.0
+        },
+    }
 }
 
 impl Hash for Node {
@@ -66,20 +121,114 @@ pub struct Edge {
     end_node: Arc<RwLock<Node>>,
 }
 
+// Point-in-time copy of a node's state, as returned by MorassWeb::snapshot_nodes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeSnapshot {
+    pub id: usize,
+    pub charge: f64,
+    pub threshold: f64,
+    pub cooldown_remaining: usize,
+    pub since_last_fire: usize,
+}
+
+// Parameters given to newly created edges
+// Health, fire_within and end_node_fire_within are copied onto each edge, while out_percentage and out_fixed
+// are drawn uniformly from their [min, max) ranges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeConfig {
+    pub health: usize,
+    pub fire_within: usize,
+    pub end_node_fire_within: usize,
+    pub out_percentage_range: (f64, f64),
+    pub out_fixed_range: (f64, f64),
+}
+
+impl Default for EdgeConfig {
+    fn default() -> Self {
+        Self {
+            health: 3,
+            fire_within: 5,
+            end_node_fire_within: 3,
+            out_percentage_range: (0.0, 1.0),
+            out_fixed_range: (0.0, 5.0),
+        }
+    }
+}
+
+impl EdgeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        // penalise takes last_fire modulo fire_within
+        if self.fire_within == 0 {
+            return Err("fire_within must be greater than 0".to_string());
+        }
+        if self.end_node_fire_within == 0 {
+            return Err("end_node_fire_within must be greater than 0".to_string());
+        }
+        for (name, (min, max)) in [("out_percentage_range", self.out_percentage_range), ("out_fixed_range", self.out_fixed_range)] {
+            if min.is_nan() || max.is_nan() || min > max {
+                return Err(format!("{} must have min <= max, got ({}, {})", name, min, max));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Number of consecutive quiet steps after which run considers the web settled
+const SETTLE_STEPS: usize = 10;
+
+// Upper bound on out_percentage when Hebbian learning strengthens an edge
+// Edges configured above it keep their weight rather than being pulled down to it
+const HEBBIAN_MAX_OUT_PERCENTAGE: f64 = 1.0;
+
+// Summary of a single step, as returned by MorassWeb::step_with_stats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepStats {
+    pub fired_edges: usize, // Edges that carried a pulse, i.e. the increase in the op counter
+    pub nodes_fired: usize, // Nodes that met their threshold and had their charge consumed
+    pub edges_removed: usize, // Edges pruned because their health reached 0
+    pub total_charge: f64, // Sum of all node charges after assimilation
+    pub mean_charge: f64,
+}
+
+// Sent to the on_fire callback whenever an edge carries a pulse
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FireEvent {
+    pub start_id: usize, // Ids are only valid until the next remove_node, which shifts later ids down by one
+    pub end_id: usize,
+    pub pulse: f64,
+    pub step: usize, // Number of steps completed before the one in which the edge fired
+}
+
 pub struct MorassWeb {
     nodes: Vec<Arc<RwLock<Node>>>,
     edges: Vec<Arc<RwLock<Edge>>>,
     node_temp_charges: Vec<Arc<RwLock<f64>>>,
     node_last_fired: Vec<Arc<RwLock<usize>>>,
-    pairs: Arc<RwLock<HashSet<(usize, usize)>>>, // usize representation of edges
+    pairs: Arc<RwLock<HashSet<(usize, usize)>>>, // (start, end) of each edge as 0-based indices into nodes, i.e. id - 1
     op_counter: Arc<RwLock<usize>>,
     edges_added_counter: Arc<RwLock<usize>>,
+    learning_rate: Option<f64>, // Hebbian learning rate for edge out_percentage, disabled when None
+    step_counter: usize,
+    out_degrees: Vec<usize>, // Number of outgoing edges for each node index, kept in sync with edges
+    edge_config: EdgeConfig,
+    on_fire: Option<Arc<dyn Fn(FireEvent) + Send + Sync>>, // Called from the parallel pulse phase
+    rng: Mutex<StdRng>, // Used for everything random after construction, so seeded webs stay reproducible
 }
 
 
 
 impl MorassWeb {
     pub fn make_random_web(num_nodes: usize, num_edges: usize) -> Self {
+        MorassWeb::make_web_with_rng(num_nodes, num_edges, StdRng::from_entropy())
+    }
+
+    // Same as make_random_web, but the same seed and parameters always produce the same web
+    // The generator is kept on the web, so edges added later with add_edges_to_random_node are reproducible too
+    pub fn make_random_web_seeded(num_nodes: usize, num_edges: usize, seed: u64) -> Self {
+        MorassWeb::make_web_with_rng(num_nodes, num_edges, StdRng::seed_from_u64(seed))
+    }
+
+    fn make_web_with_rng(num_nodes: usize, num_edges: usize, mut rng: StdRng) -> Self {
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
         let mut node_temp_charges = Vec::new();
@@ -89,15 +238,17 @@ impl MorassWeb {
             // Create node with random parameters
             let node = Node {
                 id: n + 1,
-                threshold: random::<f64>() * 10.0,
-                charge: random::<f64>() * 5.0,
-                cooldown: random::<usize>() % 5 + 1,
+                threshold: rng.gen::<f64>() * 10.0,
+                charge: rng.gen::<f64>() * 5.0,
+                cooldown: rng.gen::<usize>() % 5 + 1,
                 cooldown_remaining: 0,
                 since_last_fire: 0,
-                charge_consumption_percentage: random::<f64>() * 20.0,
-                charge_consumption_fixed: random::<f64>() * 3.0,
-                decay_percentage: random::<f64>() * 0.05,
-                decay_fixed: random::<f64>() * 0.2,
+                charge_consumption_percentage: rng.gen::<f64>() * 20.0,
+                charge_consumption_fixed: rng.gen::<f64>() * 3.0,
+                decay_percentage: rng.gen::<f64>() * 0.05,
+                decay_fixed: rng.gen::<f64>() * 0.2,
+                resting_potential: 0.0,
+                activation: Activation::Hard,
             };
             let rc_node = Arc::new(RwLock::new(node));
             nodes.push(Arc::clone(&rc_node));
@@ -111,37 +262,38 @@ impl MorassWeb {
         // Make n random pairs of integers where n=num_edges and each integer is in the range [0, num_nodes)
         // Each pair is unique, (i, i) is not allowed, and (i, j) is the same as (j, i)
         let pairs: Arc<RwLock<HashSet<(usize, usize)>>> = Arc::new(RwLock::new(HashSet::new()));
+        // Keep the pairs in generation order as well, since HashSet iteration order differs between runs
+        let mut ordered_pairs = Vec::new();
         let mut tries = 0;
-        for _ in 0..num_edges {
+        'pairs: for _ in 0..num_edges {
             let pair = loop {
                 tries += 1;
-                let ret = (random::<usize>() % num_nodes, random::<usize>() % num_nodes);
+                let ret = (rng.gen::<usize>() % num_nodes, rng.gen::<usize>() % num_nodes);
                 if ret.0 != ret.1 && !pairs.read().unwrap().contains(&ret) && !pairs.read().unwrap().contains(&(ret.1, ret.0)) {
                     break ret;
                 };
                 if tries > 1000 {
                     println!("Could not find {} unique pairs", num_edges);
                     println!("Found {} unique pairs", pairs.read().unwrap().len());
-                    return Self {
-                        nodes,
-                        edges,
-                        node_temp_charges,
-                        node_last_fired,
-                        pairs,
-                        op_counter: Arc::new(RwLock::new(0)),
-                        edges_added_counter: Arc::new(RwLock::new(0)),
-                    };
+                    // Still build edges for the pairs that were found so pairs and edges stay in sync
+                    break 'pairs;
                 }
             };
             pairs.write().unwrap().insert(pair);
+            ordered_pairs.push(pair);
         }
 
         // Create edge with random parameters
-        for pair in pairs.read().unwrap().iter() {
+        let edge_config = EdgeConfig::default();
+        let mut out_degrees = vec![0; num_nodes];
+        for pair in ordered_pairs {
             // Create edge with random parameters
-            let edge = MorassWeb::default_edge(nodes.get(pair.0).unwrap(),
-                                               nodes.get(pair.1).unwrap());
+            let edge = MorassWeb::edge_from_config(&edge_config,
+                                                   nodes.get(pair.0).unwrap(),
+                                                   nodes.get(pair.1).unwrap(),
+                                                   &mut rng);
             edges.push(Arc::new(RwLock::new(edge)));
+            out_degrees[pair.0] += 1;
         }
 
         Self {
@@ -152,18 +304,26 @@ impl MorassWeb {
             pairs,
             op_counter: Arc::new(RwLock::new(0)),
             edges_added_counter: Arc::new(RwLock::new(0)),
+            learning_rate: None,
+            step_counter: 0,
+            out_degrees,
+            edge_config,
+            on_fire: None,
+            rng: Mutex::new(rng),
         }
     }
 
-    fn assimilate(&mut self, node: &Arc<RwLock<Node>>) {
+    // Returns the node's charge after assimilation
+    fn assimilate(&self, node: &Arc<RwLock<Node>>) -> f64 {
         let mut node = node.write().unwrap();
         node.charge = self.node_temp_charges[node.id-1].read().unwrap().add(node.charge);
         self.node_temp_charges[node.id-1].write().unwrap().clone_from(&0.0);
+        node.charge
     }
 
-    fn pulse(&mut self, edge: &Arc<RwLock<Edge>>, verbose: bool) -> bool {
+    fn pulse(&self, edge: &Arc<RwLock<Edge>>, verbose: bool) -> bool {
         // Read phase
-        let (start_node_charge, start_node_threshold, start_node_cooldown, out_percentage, out_fixed, end_node_cooldown, end_node_since_last_fire, last_fire) = {
+        let (start_node_charge, start_node_threshold, start_node_activation, out_percentage, out_fixed, last_fire) = {
             let edge_read = edge.read().unwrap();
             let start_node_read = edge_read.start_node.read().unwrap();
 
@@ -171,41 +331,41 @@ impl MorassWeb {
             if start_node_read.cooldown_remaining > 0 {
                 return false;
             }
-            let end_node_read = edge_read.end_node.read().unwrap();
 
             (
                 start_node_read.charge,
                 start_node_read.threshold,
-                start_node_read.cooldown,
+                start_node_read.activation,
                 edge_read.out_percentage,
                 edge_read.out_fixed,
-                end_node_read.cooldown,
-                end_node_read.since_last_fire,
                 edge_read.last_fire,
             )
         };
 
         // Compute pulse
-        let pulse = if start_node_charge >= start_node_threshold {
-            start_node_charge * out_percentage + out_fixed
-        } else {
-            0.0
-        };
+        let pulse = activation_output(start_node_charge, start_node_threshold, start_node_activation, out_percentage, out_fixed);
 
         // Write phase
-        if pulse > 0.0 {
-            'startnode: loop {
+        let fired = pulse > 0.0 && start_node_charge >= start_node_threshold;
+        if pulse > 0.0 && !fired {
+            // Sub-threshold leak: pass the charge on without the edge counting as fired
+            let edge_read = edge.read().unwrap();
+            let end_id = edge_read.end_node.read().unwrap().id;
+            self.node_temp_charges[end_id-1].write().unwrap().add_assign(&pulse);
+        }
+        if fired {
+            let start_id = 'startnode: loop {
                 let edge_read = edge.read().unwrap();
                 let start_node_lock = edge_read.start_node.read();
                 if start_node_lock.is_err() {
                     continue 'startnode;
                 }
                 let read_start_node = start_node_lock.unwrap();
-                self.node_temp_charges[read_start_node.id-1].write().unwrap().add_assign(&pulse);
+                // The pulse only goes to the end node; the start node's own charge is consumed in subtract_charge
                 self.node_last_fired[read_start_node.id-1].write().unwrap().clone_from(&0);
-                break 'startnode;
-            }
-            'endnode: loop {
+                break 'startnode read_start_node.id;
+            };
+            let end_id = 'endnode: loop {
                 let edge_read = edge.read().unwrap();
                 let end_node_lock = edge_read.end_node.read();
                 if end_node_lock.is_err() {
@@ -214,22 +374,31 @@ impl MorassWeb {
                 let read_end_node = end_node_lock.unwrap();
                 self.node_temp_charges[read_end_node.id-1].write().unwrap().add_assign(&pulse);
                 self.node_last_fired[read_end_node.id-1].write().unwrap().clone_from(&0);
-                break 'endnode;
-            }
+                break 'endnode read_end_node.id;
+            };
             {
                 let mut write_edge = edge.write().unwrap();
                 write_edge.last_fire = 0;
+                // Strengthen edges that successfully carry a signal
+                if let Some(rate) = self.learning_rate {
+                    let cap = HEBBIAN_MAX_OUT_PERCENTAGE.max(write_edge.out_percentage);
+                    write_edge.out_percentage = (write_edge.out_percentage + rate).min(cap);
+                }
             }
 
             if verbose {
                 println!(
                     "Node {} fired on edge {}->{} with pulse {}",
-                    edge.read().unwrap().start_node.read().unwrap().id,
-                    edge.read().unwrap().start_node.read().unwrap().id,
-                    edge.read().unwrap().end_node.read().unwrap().id,
+                    start_id,
+                    start_id,
+                    end_id,
                     pulse
                 );
             }
+            // No locks are held here, so the callback is free to read the web
+            if let Some(on_fire) = &self.on_fire {
+                on_fire(FireEvent { start_id, end_id, pulse, step: self.step_counter });
+            }
 
             true
         } else {
@@ -241,57 +410,107 @@ impl MorassWeb {
 
 
     pub fn step(&mut self, verbose: bool) {
+        self.step_with_stats(verbose);
+    }
+
+    // Same as step, but also reports what happened during the step
+    pub fn step_with_stats(&mut self, verbose: bool) -> StepStats {
         let op_counter: usize = self.edges.par_iter()
             .map(|edge| {
                 if self.pulse(edge, verbose) { 1 } else { 0 }
             })
             .sum();
 
-        self.nodes.par_iter().for_each(|node| {
+        let (nodes_fired, total_charge) = self.nodes.par_iter().map(|node| {
             // println!("About to subtract charge");
-            MorassWeb::subtract_charge(node);
+            let fired = MorassWeb::subtract_charge(node);
             // println!("About to decay");
             MorassWeb::decay(node);
             // println!("About to assimilate");
-            self.assimilate(node);
+            let charge = self.assimilate(node);
             // println!("About to cooldown");
             MorassWeb::cooldown_step(node);
-        });
+            (if fired { 1 } else { 0 }, charge)
+        }).reduce(|| (0, 0.0), |a, b| (a.0 + b.0, a.1 + b.1));
 
         self.edges.par_iter().for_each(|edge| {
             // println!("About to penalise");
-            MorassWeb::penalise(edge);
+            MorassWeb::penalise(edge, self.learning_rate);
         });
         // println!("About to retain");
-        self.pairs.write().unwrap().retain(|pair| {
-            let start_node = self.nodes.get(pair.0).unwrap();
-            let end_node = self.nodes.get(pair.1).unwrap();
-            let start_node_read = start_node.read().unwrap();
-            let end_node_read = end_node.read().unwrap();
-            start_node_read.cooldown_remaining <= 0 && end_node_read.cooldown_remaining <= 0
-        });
-
         // Handling self.edges.retain in parallel might be complex due to mutable references
-        self.edges.retain(|edge| edge.read().unwrap().edge_health > 0);
+        // Pairs are dropped together with their edge so that the pair can be reconnected later
+        let prior_total_edges = self.edges.len();
+        let out_degrees = &mut self.out_degrees;
+        let mut pairs = self.pairs.write().unwrap();
+        self.edges.retain(|edge| {
+            let edge = edge.read().unwrap();
+            if edge.edge_health > 0 {
+                return true;
+            }
+            let start_index = edge.start_node.read().unwrap().id - 1;
+            pairs.remove(&(start_index, edge.end_node.read().unwrap().id - 1));
+            out_degrees[start_index] -= 1;
+            false
+        });
+        drop(pairs);
         let mut op_lock = self.op_counter.write().unwrap();
         *op_lock += op_counter;
+        drop(op_lock);
+        self.step_counter += 1;
         // println!("finished step");
+
+        StepStats {
+            fired_edges: op_counter,
+            nodes_fired,
+            edges_removed: prior_total_edges - self.edges.len(),
+            total_charge,
+            mean_charge: if self.nodes.is_empty() { 0.0 } else { total_charge / self.nodes.len() as f64 },
+        }
+    }
+
+    // Step up to max_steps times, stopping early once the web has settled
+    // The web is settled when, for SETTLE_STEPS steps in a row, the total charge changed by less than settle_tol
+    // and no edges were pruned. Returns the number of steps taken
+    pub fn run(&mut self, max_steps: usize, settle_tol: f64) -> usize {
+        let mut last_total_charge: Option<f64> = None;
+        let mut quiet_steps = 0;
+        for taken in 1..=max_steps {
+            let stats = self.step_with_stats(false);
+            let settled = match last_total_charge {
+                Some(last) => stats.edges_removed == 0 && (stats.total_charge - last).abs() < settle_tol,
+                None => false,
+            };
+            quiet_steps = if settled { quiet_steps + 1 } else { 0 };
+            if quiet_steps >= SETTLE_STEPS {
+                return taken;
+            }
+            last_total_charge = Some(stats.total_charge);
+        }
+        max_steps
     }
 
     fn cooldown_step(node: &Arc<RwLock<Node>>) {
         let mut node = node.write().unwrap();
         if node.cooldown_remaining > 0 {
-            node.cooldown_remaining = node.cooldown_remaining - 1;
+            node.cooldown_remaining -= 1;
         }
     }
 
-    fn penalise(edge: &Arc<RwLock<Edge>>) {
+    fn penalise(edge: &Arc<RwLock<Edge>>, learning_rate: Option<f64>) {
         let mut edge = edge.write().unwrap();
+        let mut penalties = 0;
         if edge.end_node.read().unwrap().since_last_fire == edge.end_node_fire_within { // Only penalise once
             edge.edge_health = max(edge.edge_health, 1) - 1;
+            penalties += 1;
         }
         if edge.last_fire % edge.fire_within == edge.fire_within-1 {
             edge.edge_health = max(edge.edge_health, 1) - 1;
+            penalties += 1;
+        }
+        // Weaken the edge once for every penalty it received
+        if let Some(rate) = learning_rate {
+            edge.out_percentage = (edge.out_percentage - rate * penalties as f64).max(0.0);
         }
     }
 
@@ -299,42 +518,124 @@ impl MorassWeb {
 
     fn decay(node: &Arc<RwLock<Node>>) {
         let mut node = node.write().unwrap();
-        node.charge = node.charge - node.charge * node.decay_percentage - node.decay_fixed;
+        let decayed = node.charge - node.charge * node.decay_percentage - node.decay_fixed;
+        // Decay never takes the charge below the resting potential, but a deficit left by firing is not lifted
+        // back up to it, since that deficit is what holds off re-firing
+        node.charge = decayed.max(node.resting_potential.min(node.charge));
     }
 
-    fn subtract_charge(node: &Arc<RwLock<Node>>) {
+    // Returns true if the node fired
+    fn subtract_charge(node: &Arc<RwLock<Node>>) -> bool {
         // If the node is on cooldown, skip it
         let mut node = node.write().unwrap();
-        if node.cooldown_remaining <= 0 {
-            if node.charge >= node.threshold {
-                node.charge =
-                    node.charge
-                        - node.charge * node.charge_consumption_percentage
-                        - node.charge_consumption_fixed;
-            }
+        if node.cooldown_remaining == 0 && node.charge >= node.threshold {
+            node.charge =
+                node.charge
+                    - node.charge * node.charge_consumption_percentage
+                    - node.charge_consumption_fixed;
+            return true;
         }
+        false
     }
 
 
 
-    pub fn default_edge(start_node: &Arc<RwLock<Node>>, end_node: &Arc<RwLock<Node>>) -> Edge {
+    // Create an edge using the web's edge config
+    pub fn default_edge(&self, start_node: &Arc<RwLock<Node>>, end_node: &Arc<RwLock<Node>>) -> Edge {
+        self.default_edge_with_rng(start_node, end_node, &mut *self.rng.lock().unwrap())
+    }
+
+    // Same as default_edge, but draws the random edge parameters from the given generator
+    pub fn default_edge_with_rng<R: Rng + ?Sized>(&self, start_node: &Arc<RwLock<Node>>, end_node: &Arc<RwLock<Node>>, rng: &mut R) -> Edge {
+        MorassWeb::edge_from_config(&self.edge_config, start_node, end_node, rng)
+    }
+
+    // Free-standing form of default_edge for building edges without a web, e.g. before one exists
+    pub fn edge_from_config<R: Rng + ?Sized>(cfg: &EdgeConfig, start_node: &Arc<RwLock<Node>>, end_node: &Arc<RwLock<Node>>, rng: &mut R) -> Edge {
+        let (op_min, op_max) = cfg.out_percentage_range;
+        let (of_min, of_max) = cfg.out_fixed_range;
         Edge {
-            out_percentage: random::<f64>(),
-            out_fixed: random::<f64>() * 5.0,
-            edge_health: 3,
+            out_percentage: op_min + rng.gen::<f64>() * (op_max - op_min),
+            out_fixed: of_min + rng.gen::<f64>() * (of_max - of_min),
+            edge_health: cfg.health,
             last_fire: 0,
-            fire_within: 5,
-            end_node_fire_within: 3,
+            fire_within: cfg.fire_within,
+            end_node_fire_within: cfg.end_node_fire_within,
             start_node: Arc::clone(start_node),
             end_node: Arc::clone(end_node),
         }
     }
 
+    // Replace the parameters used for new edges; existing edges are left unchanged
+    pub fn set_edge_config(&mut self, cfg: EdgeConfig) -> Result<(), String> {
+        cfg.validate()?;
+        self.edge_config = cfg;
+        Ok(())
+    }
+
+    // Enable Hebbian learning: edges that fire gain rate in out_percentage (up to 1.0)
+    // and every edge_health penalty takes rate away again (down to 0.0)
+    pub fn enable_hebbian(&mut self, rate: f64) -> Result<(), String> {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(format!("learning rate must be finite and at least 0, got {}", rate));
+        }
+        self.learning_rate = Some(rate);
+        Ok(())
+    }
+
+    // Register a callback that receives every firing event; it is called concurrently from multiple threads
+    pub fn set_on_fire<F: Fn(FireEvent) + Send + Sync + 'static>(&mut self, cb: F) {
+        self.on_fire = Some(Arc::new(cb));
+    }
+
+    // Restart the web's random generator from a seed, e.g. to make a loaded web reproducible again
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+    }
+
     pub fn inject_node_index(&self, index: usize, input: f64) {
         let mut node = self.nodes[index].write().unwrap();
         node.charge += input;
     }
 
+    fn node_by_id(&self, id: usize) -> Result<&Arc<RwLock<Node>>, String> {
+        id.checked_sub(1)
+            .and_then(|index| self.nodes.get(index))
+            .ok_or_else(|| format!("No node with id {} (valid ids are 1 to {})", id, self.nodes.len()))
+    }
+
+    // Add input to the charge of the node with the given id
+    pub fn inject_node(&self, id: usize, input: f64) -> Result<(), String> {
+        let mut node = self.node_by_id(id)?.write().unwrap();
+        node.charge += input;
+        Ok(())
+    }
+
+    // Inject a batch of (id, input) pairs; if any id is invalid nothing is injected
+    pub fn inject_many(&self, inputs: &[(usize, f64)]) -> Result<(), String> {
+        let nodes = inputs
+            .iter()
+            .map(|&(id, input)| Ok((self.node_by_id(id)?, input)))
+            .collect::<Result<Vec<_>, String>>()?;
+        for (node, input) in nodes {
+            node.write().unwrap().charge += input;
+        }
+        Ok(())
+    }
+
+    pub fn set_node_activation(&self, id: usize, act: Activation) -> Result<(), String> {
+        act.validate()?;
+        let mut node = self.node_by_id(id)?.write().unwrap();
+        node.activation = act;
+        Ok(())
+    }
+
+    pub fn set_resting_potential(&self, id: usize, resting_potential: f64) -> Result<(), String> {
+        let mut node = self.node_by_id(id)?.write().unwrap();
+        node.resting_potential = resting_potential;
+        Ok(())
+    }
+
 
     // Show the current charge of all nodes
     pub fn show_nodes(&self) {
@@ -358,83 +659,413 @@ impl MorassWeb {
         }
     }
 
+    pub fn get_node_charge(&self, index: usize) -> Option<f64> {
+        self.nodes.get(index).map(|node| node.read().unwrap().charge)
+    }
+
+    pub fn get_node_threshold(&self, index: usize) -> Option<f64> {
+        self.nodes.get(index).map(|node| node.read().unwrap().threshold)
+    }
+
+    // Copy out the current state of every node, in the same order as the node indices
+    pub fn snapshot_nodes(&self) -> Vec<NodeSnapshot> {
+        self.nodes.iter().map(|node| {
+            let node = node.read().unwrap();
+            NodeSnapshot {
+                id: node.id,
+                charge: node.charge,
+                threshold: node.threshold,
+                cooldown_remaining: node.cooldown_remaining,
+                since_last_fire: node.since_last_fire,
+            }
+        }).collect()
+    }
+
+    // Copy out every edge as (start id, end id, out_percentage, out_fixed, edge_health)
+    pub fn snapshot_edges(&self) -> Vec<(usize, usize, f64, f64, usize)> {
+        self.edges.iter().map(|edge| {
+            let edge = edge.read().unwrap();
+            let start_id = edge.start_node.read().unwrap().id;
+            let end_id = edge.end_node.read().unwrap().id;
+            (
+                start_id,
+                end_id,
+                edge.out_percentage,
+                edge.out_fixed,
+                edge.edge_health,
+            )
+        }).collect()
+    }
+
+    // Render the current topology as a GraphViz digraph
+    // Nodes on cooldown are drawn dashed and grey, and edges get thinner and lighter as their health drops
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph morass {\n");
+        for node in &self.nodes {
+            let node = node.read().unwrap();
+            let style = if node.cooldown_remaining > 0 {
+                ", style=dashed, color=gray60, fontcolor=gray60"
+            } else {
+                ""
+            };
+            let _ = writeln!(out, "    n{} [label=\"{}\\n{:.2}\"{}];", node.id, node.id, node.charge, style);
+        }
+        for edge in &self.edges {
+            let edge = edge.read().unwrap();
+            let max_health = max(self.edge_config.health, 1);
+            let health = std::cmp::min(edge.edge_health, max_health);
+            let _ = writeln!(
+                out,
+                "    n{} -> n{} [label=\"{:.2}\", penwidth={}, color=gray{}];",
+                edge.start_node.read().unwrap().id,
+                edge.end_node.read().unwrap().id,
+                edge.out_percentage,
+                0.5 + 1.5 * health as f64 / max_health as f64,
+                80 - health * 80 / max_health
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
     pub fn get_op_counter(&self) -> usize {
-        self.op_counter.read().unwrap().clone()
+        *self.op_counter.read().unwrap()
     }
 
     pub fn get_added_edges(&self) -> usize {
-        self.edges_added_counter.read().unwrap().clone()
+        *self.edges_added_counter.read().unwrap()
     }
 
-    pub fn add_edges_to_random_node(&mut self, num_edges: usize, max_tries: usize) {
-        let mut tries = 0;
-        let prior_total_edges = self.edges.len();
-        // Tally the number of outgoing edges for each node
-        let mut arr_count = vec![0; self.nodes.len()];
-        for edge in &self.edges {
+    // Add an unconnected node with no charge and return its id
+    pub fn add_node(&mut self, threshold: f64, cooldown: usize, cp: f64, cf: f64, dp: f64, df: f64) -> usize {
+        let id = self.nodes.len() + 1;
+        let node = Node {
+            id,
+            threshold,
+            charge: 0.0,
+            cooldown,
+            cooldown_remaining: 0,
+            since_last_fire: 0,
+            charge_consumption_percentage: cp,
+            charge_consumption_fixed: cf,
+            decay_percentage: dp,
+            decay_fixed: df,
+            resting_potential: 0.0,
+            activation: Activation::Hard,
+        };
+        self.nodes.push(Arc::new(RwLock::new(node)));
+        self.node_temp_charges.push(Arc::new(RwLock::new(0.0)));
+        self.node_last_fired.push(Arc::new(RwLock::new(0)));
+        self.out_degrees.push(0);
+        id
+    }
+
+    // Remove a node along with every edge touching it, returning false if there is no node with that id
+    // Node ids always match their position (id == index + 1), so ids are not tombstoned: every node after
+    // the removed one has its id shifted down by one, and the next add_node reuses the highest id.
+    // Any ids held by the caller (including those from earlier FireEvents) above the removed id must be
+    // decremented by one
+    pub fn remove_node(&mut self, id: usize) -> bool {
+        if id == 0 || id > self.nodes.len() {
+            return false;
+        }
+        let index = id - 1;
+        let removed = self.nodes.remove(index);
+        self.node_temp_charges.remove(index);
+        self.node_last_fired.remove(index);
+        // Ids have not been shifted yet, so id - 1 still gives each node's original index here
+        let out_degrees = &mut self.out_degrees;
+        self.edges.retain(|edge| {
             let edge = edge.read().unwrap();
-            arr_count[edge.start_node.read().unwrap().id - 1] += 1;
+            if !Arc::ptr_eq(&edge.start_node, &removed) && !Arc::ptr_eq(&edge.end_node, &removed) {
+                return true;
+            }
+            out_degrees[edge.start_node.read().unwrap().id - 1] -= 1;
+            false
+        });
+        self.out_degrees.remove(index);
+
+        for (i, node) in self.nodes.iter().enumerate().skip(index) {
+            node.write().unwrap().id = i + 1;
         }
+        let mut pairs = self.pairs.write().unwrap();
+        let shift = |i: usize| if i > index { i - 1 } else { i };
+        *pairs = pairs
+            .iter()
+            .filter(|&&(a, b)| a != index && b != index)
+            .map(|&(a, b)| (shift(a), shift(b)))
+            .collect();
+        true
+    }
+
+    pub fn add_edges_to_random_node(&mut self, num_edges: usize, max_tries: usize) {
+        let mut tries = 0;
 
         // Identify the nodes that can have edges added
         let available_nodes: Vec<usize> =
-            arr_count.iter().enumerate().filter_map(|(i, &x)| if x < self.nodes.len() - 1 {
+            self.out_degrees.iter().enumerate().filter_map(|(i, &x)| if x < self.nodes.len() - 1 {
                 Some(i)
             } else {
                 None
             }).collect();
-        if available_nodes.len() == 0 {
+        if available_nodes.is_empty() {
             // println!("No nodes can have any more edges");
             return;
         }
-        'main: while tries < max_tries {
+        while tries < max_tries {
             // Randomly pick from the available nodes
-            let _available_target_node_index = random::<usize>() % available_nodes.len();
+            let _available_target_node_index = self.rng.lock().unwrap().gen::<usize>() % available_nodes.len();
             let target_node_index = available_nodes[_available_target_node_index];
-            let mut loop_tries = 0;
-            // Calculate the number of existing edges
-            let existing_edges = arr_count[target_node_index];
-
-            let unconnected_nodes: Vec<usize> = (0..self.nodes.len()).filter_map(|i| {
-                if !self.pairs.read().unwrap().contains(&(target_node_index, i+1)) {
-                    Some(i)
-                } else {
-                    None
-                }
-            }).collect();
 
-            // Calculate the maximum number of new edges that can be added
-            let max_new_edges = unconnected_nodes.len();
-            let edges_to_add = std::cmp::min(num_edges, max_new_edges);
-
-            if edges_to_add == 0 {
+            if self.add_edges_to_index(target_node_index, num_edges) == 0 {
                 // This node cannot have any more edges, try another node
                 tries += 1;
-                continue 'main;
+                continue;
             }
 
-            for i in 0..edges_to_add {
-                let end_node_index = unconnected_nodes[i];
+            // Successfully added edges, no need to try more
+            break;
+        }
+    }
 
-                let edge = MorassWeb::default_edge(
-                    &self.nodes[target_node_index],
-                    &self.nodes[end_node_index]
-                );
-                self.edges.push(Arc::new(RwLock::new(edge)));
-                self.pairs.write().unwrap().insert((target_node_index, end_node_index));
-                let mut edge_count_lock = self.edges_added_counter.write().unwrap();
-                *edge_count_lock += 1;
+    // Add up to num_edges outgoing edges from the node with the given id to nodes it is not yet connected to
+    // Returns the number of edges that were added
+    pub fn add_edges_to_node(&mut self, id: usize, num_edges: usize) -> Result<usize, String> {
+        self.node_by_id(id)?;
+        Ok(self.add_edges_to_index(id - 1, num_edges))
+    }
+
+    fn add_edges_to_index(&mut self, target_node_index: usize, num_edges: usize) -> usize {
+        // Skip the node itself and any node already connected to it in either direction
+        let unconnected_nodes: Vec<usize> = {
+            let pairs = self.pairs.read().unwrap();
+            (0..self.nodes.len()).filter(|&i| {
+                i != target_node_index
+                    && !pairs.contains(&(target_node_index, i))
+                    && !pairs.contains(&(i, target_node_index))
+            }).collect()
+        };
+
+        // Calculate the maximum number of new edges that can be added
+        let edges_to_add = std::cmp::min(num_edges, unconnected_nodes.len());
+
+        for &end_node_index in unconnected_nodes.iter().take(edges_to_add) {
+            let edge = self.default_edge(
+                &self.nodes[target_node_index],
+                &self.nodes[end_node_index]
+            );
+            self.edges.push(Arc::new(RwLock::new(edge)));
+            self.pairs.write().unwrap().insert((target_node_index, end_node_index));
+            self.out_degrees[target_node_index] += 1;
+            let mut edge_count_lock = self.edges_added_counter.write().unwrap();
+            *edge_count_lock += 1;
+        }
+        edges_to_add
+    }
+
+    // Save the full state of the network (nodes, edges, counters and per-step buffers) as JSON
+    pub fn save_to_json(&self, path: &str) -> std::io::Result<()> {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = write!(
+            out,
+            "{{\"op_counter\":{},\"edges_added_counter\":{},\"step_counter\":{},\"nodes\":[",
+            self.get_op_counter(),
+            self.get_added_edges(),
+            self.step_counter
+        );
+        for (i, node) in self.nodes.iter().enumerate() {
+            let node = node.read().unwrap();
+            let _ = write!(
+                out,
+                "{}\n{{\"id\":{},\"threshold\":{},\"charge\":{},\"cooldown\":{},\"cooldown_remaining\":{},\
+                 \"since_last_fire\":{},\"charge_consumption_percentage\":{},\"charge_consumption_fixed\":{},\
+                 \"decay_percentage\":{},\"decay_fixed\":{},\"resting_potential\":{},\"activation\":{}}}",
+                if i == 0 { "" } else { "," },
+                node.id,
+                json::Float(node.threshold),
+                json::Float(node.charge),
+                node.cooldown,
+                node.cooldown_remaining,
+                node.since_last_fire,
+                json::Float(node.charge_consumption_percentage),
+                json::Float(node.charge_consumption_fixed),
+                json::Float(node.decay_percentage),
+                json::Float(node.decay_fixed),
+                json::Float(node.resting_potential),
+                // Variants are written as a single-key object named after the variant
+                match node.activation {
+                    Activation::Hard => "{\"hard\":{}}".to_string(),
+                    Activation::Sigmoid { steepness } => format!("{{\"sigmoid\":{{\"steepness\":{}}}}}", json::Float(steepness)),
+                    Activation::ReLU => "{\"relu\":{}}".to_string(),
+                }
+            );
+        }
+        out.push_str("],\"edges\":[");
+        for (i, edge) in self.edges.iter().enumerate() {
+            let edge = edge.read().unwrap();
+            let _ = write!(
+                out,
+                "{}\n{{\"start_node\":{},\"end_node\":{},\"out_percentage\":{},\"out_fixed\":{},\
+                 \"edge_health\":{},\"last_fire\":{},\"fire_within\":{},\"end_node_fire_within\":{}}}",
+                if i == 0 { "" } else { "," },
+                edge.start_node.read().unwrap().id,
+                edge.end_node.read().unwrap().id,
+                json::Float(edge.out_percentage),
+                json::Float(edge.out_fixed),
+                edge.edge_health,
+                edge.last_fire,
+                edge.fire_within,
+                edge.end_node_fire_within
+            );
+        }
+        // Pairs are not saved since they are rebuilt from the edges on load
+        let charges: Vec<String> = self.node_temp_charges.iter().map(|c| json::Float(*c.read().unwrap()).to_string()).collect();
+        let _ = write!(out, "],\n\"node_temp_charges\":[{}]", charges.join(","));
+        let last_fired: Vec<String> = self.node_last_fired.iter().map(|f| f.read().unwrap().to_string()).collect();
+        let _ = write!(out, ",\n\"node_last_fired\":[{}]", last_fired.join(","));
+        let cfg = &self.edge_config;
+        let _ = write!(
+            out,
+            ",\n\"edge_config\":{{\"health\":{},\"fire_within\":{},\"end_node_fire_within\":{},\
+             \"out_percentage_range\":[{},{}],\"out_fixed_range\":[{},{}]}}",
+            cfg.health,
+            cfg.fire_within,
+            cfg.end_node_fire_within,
+            json::Float(cfg.out_percentage_range.0),
+            json::Float(cfg.out_percentage_range.1),
+            json::Float(cfg.out_fixed_range.0),
+            json::Float(cfg.out_fixed_range.1)
+        );
+        // learning_rate is left out entirely when Hebbian learning is disabled
+        if let Some(rate) = self.learning_rate {
+            let _ = write!(out, ",\n\"learning_rate\":{}", json::Float(rate));
+        }
+        out.push_str("}\n");
+        fs::write(path, out)
+    }
+
+    // Load a network previously written by save_to_json
+    // Callbacks and the random generator are not saved, so any on_fire callback has to be registered again
+    // and the loaded web draws from a fresh generator unless reseed is called
+    // Edges are re-linked to the loaded nodes by id so every edge shares the same Arc as its entry in nodes
+    pub fn load_from_json(path: &str) -> std::io::Result<Self> {
+        let root = json::parse(&fs::read_to_string(path)?)?;
+
+        let mut nodes = Vec::new();
+        let mut nodes_by_id = HashMap::new();
+        for value in root.get("nodes")?.as_array()? {
+            let node = Node {
+                id: value.get("id")?.as_usize()?,
+                threshold: value.get("threshold")?.as_f64()?,
+                charge: value.get("charge")?.as_f64()?,
+                cooldown: value.get("cooldown")?.as_usize()?,
+                cooldown_remaining: value.get("cooldown_remaining")?.as_usize()?,
+                since_last_fire: value.get("since_last_fire")?.as_usize()?,
+                charge_consumption_percentage: value.get("charge_consumption_percentage")?.as_f64()?,
+                charge_consumption_fixed: value.get("charge_consumption_fixed")?.as_f64()?,
+                decay_percentage: value.get("decay_percentage")?.as_f64()?,
+                decay_fixed: value.get("decay_fixed")?.as_f64()?,
+                resting_potential: value.get("resting_potential")?.as_f64()?,
+                activation: match value.get("activation")?.as_object()?.as_slice() {
+                    [(kind, _)] if kind == "hard" => Activation::Hard,
+                    [(kind, fields)] if kind == "sigmoid" => Activation::Sigmoid { steepness: fields.get("steepness")?.as_f64()? },
+                    [(kind, _)] if kind == "relu" => Activation::ReLU,
+                    _ => return Err(Error::new(ErrorKind::InvalidData, "unknown activation")),
+                },
+            };
+            node.activation.validate().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            let id = node.id;
+            // Node ids must match their position since the per-node buffers are indexed by id - 1
+            if id != nodes.len() + 1 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("expected node id {} but found {}", nodes.len() + 1, id)));
             }
+            let rc_node = Arc::new(RwLock::new(node));
+            nodes_by_id.insert(id, Arc::clone(&rc_node));
+            nodes.push(rc_node);
+        }
 
-            if self.edges.len() == prior_total_edges {
-                // No edges were added, try again
-                tries += 1;
-                continue 'main;
+        let lookup = |value: &json::Value, key: &str| -> std::io::Result<Arc<RwLock<Node>>> {
+            let id = value.get(key)?.as_usize()?;
+            nodes_by_id
+                .get(&id)
+                .map(Arc::clone)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("edge refers to unknown node id {}", id)))
+        };
+        let mut edges = Vec::new();
+        // Pairs and out degrees are not saved since they can be recounted from the edges
+        let mut pairs = HashSet::new();
+        let mut out_degrees = vec![0; nodes.len()];
+        for value in root.get("edges")?.as_array()? {
+            let edge = Edge {
+                out_percentage: value.get("out_percentage")?.as_f64()?,
+                out_fixed: value.get("out_fixed")?.as_f64()?,
+                edge_health: value.get("edge_health")?.as_usize()?,
+                last_fire: value.get("last_fire")?.as_usize()?,
+                fire_within: value.get("fire_within")?.as_usize()?,
+                end_node_fire_within: value.get("end_node_fire_within")?.as_usize()?,
+                start_node: lookup(value, "start_node")?,
+                end_node: lookup(value, "end_node")?,
+            };
+            if edge.fire_within == 0 || edge.end_node_fire_within == 0 {
+                return Err(Error::new(ErrorKind::InvalidData, "edge fire_within values must be greater than 0"));
+            }
+            let pair = (edge.start_node.read().unwrap().id - 1, edge.end_node.read().unwrap().id - 1);
+            // Same rules as make_random_web: no self loops, and (i, j) is the same as (j, i)
+            if pair.0 == pair.1 || pairs.contains(&(pair.1, pair.0)) || !pairs.insert(pair) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("duplicate or self-loop edge {}->{}", pair.0 + 1, pair.1 + 1)));
             }
+            out_degrees[pair.0] += 1;
+            edges.push(Arc::new(RwLock::new(edge)));
+        }
 
-            // Successfully added edges, no need to try more
-            break;
+        let node_temp_charges = root.get("node_temp_charges")?.as_array()?
+            .iter()
+            .map(|v| Ok(Arc::new(RwLock::new(v.as_f64()?))))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let node_last_fired = root.get("node_last_fired")?.as_array()?
+            .iter()
+            .map(|v| Ok(Arc::new(RwLock::new(v.as_usize()?))))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        if node_temp_charges.len() != nodes.len() || node_last_fired.len() != nodes.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "per-node buffers do not match the number of nodes"));
+        }
+
+        let range = |value: &json::Value, key: &str| -> std::io::Result<(f64, f64)> {
+            match value.get(key)?.as_array()?.as_slice() {
+                [min, max] => Ok((min.as_f64()?, max.as_f64()?)),
+                _ => Err(Error::new(ErrorKind::InvalidData, format!("{} must have two elements", key))),
+            }
+        };
+        let cfg = root.get("edge_config")?;
+        let edge_config = EdgeConfig {
+            health: cfg.get("health")?.as_usize()?,
+            fire_within: cfg.get("fire_within")?.as_usize()?,
+            end_node_fire_within: cfg.get("end_node_fire_within")?.as_usize()?,
+            out_percentage_range: range(cfg, "out_percentage_range")?,
+            out_fixed_range: range(cfg, "out_fixed_range")?,
+        };
+        edge_config.validate().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let learning_rate = root.get("learning_rate").ok().map(|v| v.as_f64()).transpose()?;
+        if learning_rate.is_some_and(|rate| !rate.is_finite() || rate < 0.0) {
+            return Err(Error::new(ErrorKind::InvalidData, "learning_rate must be finite and at least 0"));
         }
+
+        Ok(Self {
+            nodes,
+            edges,
+            node_temp_charges,
+            node_last_fired,
+            pairs: Arc::new(RwLock::new(pairs)),
+            op_counter: Arc::new(RwLock::new(root.get("op_counter")?.as_usize()?)),
+            edges_added_counter: Arc::new(RwLock::new(root.get("edges_added_counter")?.as_usize()?)),
+            learning_rate,
+            step_counter: root.get("step_counter")?.as_usize()?,
+            out_degrees,
+            edge_config,
+            on_fire: None,
+            rng: Mutex::new(StdRng::from_entropy()),
+        })
     }
 
     pub fn show_node_counter(&self) -> usize {
@@ -446,3 +1077,584 @@ impl MorassWeb {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("morass_web_{}_{}.json", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    // (start index, end index) of every edge, the set pairs should always match
+    fn edge_pairs(web: &MorassWeb) -> HashSet<(usize, usize)> {
+        web.snapshot_edges().iter().map(|&(start, end, ..)| (start - 1, end - 1)).collect()
+    }
+
+    fn assert_same_web(a: &MorassWeb, b: &MorassWeb) {
+        assert_eq!(a.show_node_counter(), b.show_node_counter());
+        for (x, y) in a.nodes.iter().zip(&b.nodes) {
+            assert!(*x.read().unwrap() == *y.read().unwrap());
+        }
+        assert_eq!(a.snapshot_edges(), b.snapshot_edges());
+        assert_eq!(*a.pairs.read().unwrap(), *b.pairs.read().unwrap());
+    }
+
+    #[test]
+    fn same_seed_gives_same_web() {
+        let a = MorassWeb::make_random_web_seeded(20, 40, 7);
+        let b = MorassWeb::make_random_web_seeded(20, 40, 7);
+        assert_same_web(&a, &b);
+        let c = MorassWeb::make_random_web_seeded(20, 40, 8);
+        assert_ne!(a.snapshot_edges(), c.snapshot_edges());
+    }
+
+    #[test]
+    fn same_seed_stays_equal_while_growing() {
+        let mut a = MorassWeb::make_random_web_seeded(10, 5, 11);
+        let mut b = MorassWeb::make_random_web_seeded(10, 5, 11);
+        for _ in 0..200 {
+            a.step(false);
+            b.step(false);
+            a.add_edges_to_random_node(3, 100);
+            b.add_edges_to_random_node(3, 100);
+        }
+        assert!(a.get_added_edges() > 0);
+        assert_same_web(&a, &b);
+        assert_eq!(a.get_op_counter(), b.get_op_counter());
+    }
+
+    fn assert_no_duplicate_pairs(web: &MorassWeb) {
+        let mut seen = HashSet::new();
+        for (start, end, ..) in web.snapshot_edges() {
+            assert_ne!(start, end, "self loop on node {}", start);
+            assert!(seen.insert((start.min(end), start.max(end))), "duplicate edge {}->{}", start, end);
+        }
+        assert_eq!(*web.pairs.read().unwrap(), edge_pairs(web));
+    }
+
+    #[test]
+    fn add_edges_to_node_never_duplicates() {
+        let mut web = MorassWeb::make_random_web_seeded(8, 10, 1);
+        for id in 1..=8 {
+            web.add_edges_to_node(id, 3).unwrap();
+            assert_no_duplicate_pairs(&web);
+        }
+        // Keep adding to one node until it is connected to every other node
+        web.add_edges_to_node(3, 100).unwrap();
+        assert_no_duplicate_pairs(&web);
+        assert_eq!(web.add_edges_to_node(3, 100), Ok(0));
+        for _ in 0..500 {
+            web.step(false);
+            web.add_edges_to_random_node(5, 100);
+            assert_no_duplicate_pairs(&web);
+        }
+    }
+
+    #[test]
+    fn add_edges_to_node_rejects_unknown_id() {
+        let mut web = MorassWeb::make_random_web_seeded(3, 0, 1);
+        assert!(web.add_edges_to_node(0, 1).is_err());
+        assert!(web.add_edges_to_node(4, 1).is_err());
+        assert_eq!(web.show_edge_counter(), 0);
+    }
+
+    #[test]
+    fn decay_converges_to_resting_potential() {
+        let mut web = MorassWeb::make_random_web_seeded(3, 0, 4);
+        for node in &web.nodes {
+            node.write().unwrap().threshold = f64::INFINITY;
+        }
+        web.inject_node(1, 50.0).unwrap();
+        web.set_resting_potential(2, -2.0).unwrap();
+        web.inject_node(3, 20.0).unwrap();
+        web.set_resting_potential(3, 1.0).unwrap();
+        for _ in 0..1000 {
+            web.step(false);
+        }
+        let charges: Vec<f64> = web.snapshot_nodes().iter().map(|n| n.charge).collect();
+        assert_eq!(charges, vec![0.0, -2.0, 1.0]);
+    }
+
+    #[test]
+    fn decay_leaves_a_deficit_below_resting_potential() {
+        let mut web = MorassWeb::make_random_web_seeded(1, 0, 4);
+        web.nodes[0].write().unwrap().threshold = f64::INFINITY;
+        web.inject_node(1, -5.0).unwrap();
+        let mut last = -5.0;
+        for _ in 0..100 {
+            web.step(false);
+            let charge = web.snapshot_nodes()[0].charge;
+            assert!(charge >= last && charge < 0.0, "charge went from {} to {}", last, charge);
+            last = charge;
+        }
+    }
+
+    #[test]
+    fn charges_stay_finite_on_a_growing_web() {
+        // Same shape of workload as main.rs
+        for seed in 0..3 {
+            let mut web = MorassWeb::make_random_web_seeded(10, 20, seed);
+            for step in 0..3000 {
+                web.step(false);
+                web.add_edges_to_random_node(5, 1000);
+                for node in web.snapshot_nodes() {
+                    assert!(node.charge.is_finite(), "seed {} node {} reached {} at step {}", seed, node.id, node.charge, step);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_resting_potential_rejects_unknown_id() {
+        let web = MorassWeb::make_random_web_seeded(2, 0, 4);
+        assert!(web.set_resting_potential(0, 1.0).is_err());
+        assert!(web.set_resting_potential(3, 1.0).is_err());
+    }
+
+    #[test]
+    fn accessors_match_snapshots() {
+        let web = MorassWeb::make_random_web_seeded(4, 3, 5);
+        let nodes = web.snapshot_nodes();
+        for (index, node) in nodes.iter().enumerate() {
+            assert_eq!(node.id, index + 1);
+            assert_eq!(web.get_node_charge(index), Some(node.charge));
+            assert_eq!(web.get_node_threshold(index), Some(node.threshold));
+        }
+        assert_eq!(web.get_node_charge(4), None);
+        assert_eq!(web.get_node_threshold(4), None);
+        assert_eq!(web.snapshot_edges().len(), web.show_edge_counter());
+        // Reading never changes the web
+        assert_eq!(web.snapshot_nodes(), nodes);
+    }
+
+    // Two nodes joined by a single edge 1->2 with fixed parameters and no decay
+    fn two_node_web() -> MorassWeb {
+        let mut web = MorassWeb::make_random_web_seeded(2, 0, 1);
+        web.add_edges_to_node(1, 1).unwrap();
+        for node in &web.nodes {
+            let mut node = node.write().unwrap();
+            node.threshold = 10.0;
+            node.charge = 0.0;
+            node.charge_consumption_percentage = 0.5;
+            node.charge_consumption_fixed = 0.0;
+            node.decay_percentage = 0.0;
+            node.decay_fixed = 0.0;
+        }
+        let mut edge = web.edges[0].write().unwrap();
+        edge.out_percentage = 0.5;
+        edge.out_fixed = 1.0;
+        drop(edge);
+        web
+    }
+
+    #[test]
+    fn pulse_only_reaches_end_node() {
+        let mut web = two_node_web();
+        web.nodes[1].write().unwrap().threshold = 100.0;
+        web.inject_node(1, 20.0).unwrap();
+        web.inject_node(2, 3.0).unwrap();
+        web.step(false);
+        // A keeps what subtract_charge leaves it, B gains charge * out_percentage + out_fixed
+        assert_eq!(web.get_node_charge(0), Some(20.0 - 20.0 * 0.5));
+        assert_eq!(web.get_node_charge(1), Some(3.0 + 20.0 * 0.5 + 1.0));
+    }
+
+    #[test]
+    fn hebbian_strengthens_edge_up_to_cap() {
+        let mut web = two_node_web();
+        web.enable_hebbian(0.1).unwrap();
+        let mut last = web.snapshot_edges()[0].2;
+        for _ in 0..20 {
+            web.inject_node(1, 20.0).unwrap();
+            web.step(false);
+            let out_percentage = web.snapshot_edges()[0].2;
+            assert!(out_percentage >= last);
+            last = out_percentage;
+        }
+        assert_eq!(last, HEBBIAN_MAX_OUT_PERCENTAGE);
+    }
+
+    #[test]
+    fn hebbian_keeps_weights_above_cap() {
+        let mut web = two_node_web();
+        web.edges[0].write().unwrap().out_percentage = 2.5;
+        web.enable_hebbian(0.1).unwrap();
+        web.inject_node(1, 20.0).unwrap();
+        web.step(false);
+        assert_eq!(web.snapshot_edges()[0].2, 2.5);
+    }
+
+    #[test]
+    fn hebbian_rejects_invalid_rate() {
+        let mut web = two_node_web();
+        assert!(web.enable_hebbian(-0.1).is_err());
+        assert!(web.enable_hebbian(f64::NAN).is_err());
+        assert!(web.enable_hebbian(f64::INFINITY).is_err());
+        assert_eq!(web.learning_rate, None);
+    }
+
+    #[test]
+    fn dot_export_matches_counts() {
+        let mut web = MorassWeb::make_random_web_seeded(6, 8, 6);
+        for _ in 0..3 {
+            web.step(false);
+        }
+        web.nodes[2].write().unwrap().cooldown_remaining = 2;
+        let dot = web.to_dot();
+        assert!(dot.starts_with("digraph morass {") && dot.trim_end().ends_with('}'));
+        let edge_lines = dot.lines().filter(|l| l.contains(" -> ")).count();
+        let node_lines = dot.lines().filter(|l| l.trim_start().starts_with('n') && !l.contains(" -> ")).count();
+        assert_eq!(node_lines, web.show_node_counter());
+        assert_eq!(edge_lines, web.show_edge_counter());
+        assert_eq!(dot.matches("style=dashed").count(), 1);
+    }
+
+    #[test]
+    fn added_node_takes_part_in_steps() {
+        let mut web = MorassWeb::make_random_web_seeded(4, 4, 8);
+        let id = web.add_node(1.0, 1, 0.5, 0.0, 0.0, 0.0);
+        assert_eq!(id, 5);
+        assert_eq!(web.node_temp_charges.len(), 5);
+        assert_eq!(web.node_last_fired.len(), 5);
+        // The new node only ever gets charge from incoming edges, so give it some
+        web.add_edges_to_node(1, 4).unwrap();
+        web.inject_node(1, 1000.0).unwrap();
+        web.step(false);
+        assert!(web.get_node_charge(id - 1).unwrap() > 0.0);
+        for _ in 0..50 {
+            web.step(false);
+        }
+        assert_no_duplicate_pairs(&web);
+    }
+
+    #[test]
+    fn removed_node_leaves_no_dangling_edges() {
+        let mut web = MorassWeb::make_random_web_seeded(6, 12, 2);
+        let removed = Arc::clone(&web.nodes[2]);
+        let thresholds: Vec<f64> = web.snapshot_nodes().iter().map(|n| n.threshold).collect();
+
+        assert!(web.remove_node(3));
+        assert_eq!(web.show_node_counter(), 5);
+        for edge in &web.edges {
+            let edge = edge.read().unwrap();
+            assert!(!Arc::ptr_eq(&edge.start_node, &removed) && !Arc::ptr_eq(&edge.end_node, &removed));
+        }
+        for (index, node) in web.snapshot_nodes().iter().enumerate() {
+            assert_eq!(node.id, index + 1);
+        }
+        // Node 4 is now node 3
+        assert_eq!(web.get_node_threshold(2), Some(thresholds[3]));
+        assert_no_duplicate_pairs(&web);
+        assert!(!web.remove_node(0));
+        assert!(!web.remove_node(6));
+
+        for _ in 0..50 {
+            web.step(false);
+            web.add_edges_to_random_node(2, 10);
+        }
+        assert_no_duplicate_pairs(&web);
+    }
+
+    #[test]
+    fn inject_invalid_id_changes_nothing() {
+        let web = MorassWeb::make_random_web_seeded(3, 2, 9);
+        let before = web.snapshot_nodes();
+        assert!(web.inject_node(0, 1.0).is_err());
+        assert!(web.inject_node(4, 1.0).is_err());
+        // The valid first entry must not be applied either
+        assert!(web.inject_many(&[(1, 1.0), (4, 2.0)]).is_err());
+        assert_eq!(web.snapshot_nodes(), before);
+
+        web.inject_many(&[(1, 1.0), (3, 2.0)]).unwrap();
+        let after = web.snapshot_nodes();
+        assert_eq!(after[0].charge, before[0].charge + 1.0);
+        assert_eq!(after[1].charge, before[1].charge);
+        assert_eq!(after[2].charge, before[2].charge + 2.0);
+    }
+
+    #[test]
+    fn step_stats_count_known_firing() {
+        let mut web = two_node_web();
+        web.nodes[0].write().unwrap().threshold = 15.0;
+        web.nodes[1].write().unwrap().threshold = 100.0;
+        web.inject_node(1, 20.0).unwrap();
+        let stats = web.step_with_stats(false);
+        assert_eq!(stats.fired_edges, 1);
+        assert_eq!(stats.nodes_fired, 1);
+        assert_eq!(stats.edges_removed, 0);
+        assert_eq!(stats.total_charge, 10.0 + 11.0);
+        assert_eq!(stats.mean_charge, 10.5);
+
+        // Neither node is above its threshold now
+        let stats = web.step_with_stats(false);
+        assert_eq!(stats.fired_edges, 0);
+        assert_eq!(stats.nodes_fired, 0);
+    }
+
+    #[test]
+    fn fire_callback_sees_every_fired_edge() {
+        let mut web = MorassWeb::make_random_web_seeded(10, 30, 5);
+        let events: Arc<Mutex<Vec<FireEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        web.set_on_fire(move |event| sink.lock().unwrap().push(event));
+        let before = web.get_op_counter();
+        for _ in 0..5 {
+            web.inject_many(&[(1, 20.0), (2, 20.0)]).unwrap();
+            web.step(false);
+        }
+        let events = events.lock().unwrap();
+        assert!(!events.is_empty());
+        assert_eq!(events.len(), web.get_op_counter() - before);
+        assert!(events.iter().all(|e| e.pulse > 0.0 && e.step < 5 && e.start_id != e.end_id));
+    }
+
+    fn assert_out_degrees_match(web: &MorassWeb) {
+        let mut counted = vec![0; web.show_node_counter()];
+        for (start, ..) in web.snapshot_edges() {
+            counted[start - 1] += 1;
+        }
+        assert_eq!(web.out_degrees, counted);
+    }
+
+    #[test]
+    fn cached_out_degrees_never_drift() {
+        let mut web = MorassWeb::make_random_web_seeded(12, 30, 5);
+        assert_out_degrees_match(&web);
+        let path = temp_path("out_degrees");
+        let mut edges_removed = 0;
+        for i in 0..1500 {
+            edges_removed += web.step_with_stats(false).edges_removed;
+            assert_out_degrees_match(&web);
+            web.add_edges_to_random_node(3, 50);
+            assert_out_degrees_match(&web);
+            if i % 300 == 0 {
+                web.add_edges_to_node(1, 5).unwrap();
+                assert_out_degrees_match(&web);
+                assert!(web.remove_node(1 + i % web.show_node_counter()));
+                assert_out_degrees_match(&web);
+                web.add_node(1.0, 2, 0.5, 0.1, 0.01, 0.01);
+                assert_out_degrees_match(&web);
+            }
+            if i % 500 == 0 {
+                web.save_to_json(&path).unwrap();
+                web = MorassWeb::load_from_json(&path).unwrap();
+                assert_out_degrees_match(&web);
+            }
+        }
+        fs::remove_file(&path).unwrap();
+        // Make sure step's retain was actually exercised
+        assert!(edges_removed > 0);
+    }
+
+    #[test]
+    fn edge_config_rejects_zero_fire_within() {
+        let mut web = MorassWeb::make_random_web_seeded(3, 0, 1);
+        assert!(web.set_edge_config(EdgeConfig { fire_within: 0, ..EdgeConfig::default() }).is_err());
+        assert!(web.set_edge_config(EdgeConfig { end_node_fire_within: 0, ..EdgeConfig::default() }).is_err());
+        assert!(web.set_edge_config(EdgeConfig { out_fixed_range: (2.0, 1.0), ..EdgeConfig::default() }).is_err());
+        assert_eq!(web.edge_config, EdgeConfig::default());
+        // The rejected config never reaches penalise, so stepping with new edges is fine
+        web.add_edges_to_node(1, 2).unwrap();
+        for _ in 0..10 {
+            web.step(false);
+        }
+    }
+
+    #[test]
+    fn edge_config_is_used_for_new_edges() {
+        let mut web = MorassWeb::make_random_web_seeded(4, 0, 1);
+        let cfg = EdgeConfig {
+            health: 7,
+            fire_within: 2,
+            end_node_fire_within: 4,
+            out_percentage_range: (0.2, 0.3),
+            out_fixed_range: (1.0, 1.0),
+        };
+        web.set_edge_config(cfg).unwrap();
+        web.add_edges_to_node(1, 3).unwrap();
+        for edge in &web.edges {
+            let edge = edge.read().unwrap();
+            assert_eq!((edge.edge_health, edge.fire_within, edge.end_node_fire_within), (7, 2, 4));
+            assert!((0.2..0.3).contains(&edge.out_percentage));
+            assert_eq!(edge.out_fixed, 1.0);
+        }
+    }
+
+    #[test]
+    fn hard_activation_matches_threshold_rule() {
+        assert_eq!(activation_output(20.0, 10.0, Activation::Hard, 0.5, 1.0), 11.0);
+        assert_eq!(activation_output(10.0, 10.0, Activation::Hard, 0.5, 1.0), 6.0);
+        assert_eq!(activation_output(9.99, 10.0, Activation::Hard, 0.5, 1.0), 0.0);
+        assert_eq!(activation_output(-5.0, 10.0, Activation::Hard, 0.5, 1.0), 0.0);
+    }
+
+    #[test]
+    fn sigmoid_activation_leaks_below_threshold() {
+        let sigmoid = Activation::Sigmoid { steepness: 5.0 };
+        // What Hard would send if 9.9 met the threshold
+        let full = 9.9 * 0.5 + 1.0;
+        let just_below = activation_output(9.9, 10.0, sigmoid, 0.5, 1.0);
+        assert!(just_below > 0.0 && just_below < full);
+        // Half the Hard output right at the threshold, approaching it well above
+        assert_eq!(activation_output(10.0, 10.0, sigmoid, 0.5, 1.0), 3.0);
+        assert!((activation_output(20.0, 10.0, sigmoid, 0.5, 1.0) - 11.0).abs() < 1e-9);
+        // Far below the threshold almost nothing gets through
+        assert!(activation_output(0.0, 10.0, sigmoid, 0.5, 1.0) < 1e-9);
+    }
+
+    #[test]
+    fn relu_activation_passes_excess_charge() {
+        assert_eq!(activation_output(20.0, 10.0, Activation::ReLU, 0.5, 1.0), 6.0);
+        assert_eq!(activation_output(9.0, 10.0, Activation::ReLU, 0.5, 1.0), 0.0);
+    }
+
+    #[test]
+    fn sigmoid_leak_does_not_count_as_firing() {
+        let mut web = two_node_web();
+        web.set_node_activation(1, Activation::Sigmoid { steepness: 5.0 }).unwrap();
+        web.nodes[1].write().unwrap().threshold = 100.0;
+        let events = Arc::new(Mutex::new(0));
+        let sink = Arc::clone(&events);
+        web.set_on_fire(move |_| *sink.lock().unwrap() += 1);
+        // Just below the threshold so the leak is noticeable
+        web.inject_node(1, 9.9).unwrap();
+        let stats = web.step_with_stats(false);
+        assert_eq!(stats.fired_edges, 0);
+        assert_eq!(stats.nodes_fired, 0);
+        assert!(web.get_node_charge(1).unwrap() > 0.0);
+
+        web.nodes[0].write().unwrap().charge = 0.0;
+        for _ in 0..20 {
+            web.step(false);
+        }
+        assert_eq!(web.get_op_counter(), 0);
+        assert_eq!(*events.lock().unwrap(), 0);
+        // The edge never fires, so the last_fire penalty prunes it
+        assert_eq!(web.show_edge_counter(), 0);
+    }
+
+    #[test]
+    fn set_node_activation_rejects_bad_steepness() {
+        let web = two_node_web();
+        for steepness in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(web.set_node_activation(1, Activation::Sigmoid { steepness }).is_err());
+        }
+        assert!(web.set_node_activation(3, Activation::ReLU).is_err());
+        assert_eq!(web.nodes[0].read().unwrap().activation, Activation::Hard);
+    }
+
+    #[test]
+    fn run_stops_early_once_settled() {
+        let mut web = MorassWeb::make_random_web_seeded(5, 6, 3);
+        for node in &web.nodes {
+            node.write().unwrap().decay_percentage = 0.9;
+        }
+        // Pulses are delivered after decay, so weak edges are needed for activity to die out rather than cycle
+        for edge in &web.edges {
+            let mut edge = edge.write().unwrap();
+            edge.out_percentage = 0.1;
+            edge.out_fixed = 0.0;
+        }
+        let taken = web.run(10_000, 1e-9);
+        assert!(taken < 100, "took {} steps", taken);
+    }
+
+    #[test]
+    fn run_uses_all_steps_with_continuous_input() {
+        // Node 1 never loses charge, so it feeds node 2 a fresh pulse on every step
+        let mut web = two_node_web();
+        web.nodes[0].write().unwrap().charge_consumption_percentage = 0.0;
+        web.nodes[1].write().unwrap().threshold = f64::INFINITY;
+        web.inject_node(1, 20.0).unwrap();
+        assert_eq!(web.run(200, 1e-9), 200);
+        assert_eq!(web.get_op_counter(), 200);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut web = MorassWeb::make_random_web_seeded(10, 20, 1);
+        for _ in 0..50 {
+            web.step(false);
+        }
+        let path = temp_path("round_trip");
+        web.save_to_json(&path).unwrap();
+        let loaded = MorassWeb::load_from_json(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.show_node_counter(), web.show_node_counter());
+        assert_eq!(loaded.show_edge_counter(), web.show_edge_counter());
+        assert_eq!(loaded.get_op_counter(), web.get_op_counter());
+        assert_eq!(loaded.get_added_edges(), web.get_added_edges());
+        assert_eq!(loaded.snapshot_nodes(), web.snapshot_nodes());
+        assert_eq!(loaded.snapshot_edges(), web.snapshot_edges());
+        assert_eq!(*loaded.pairs.read().unwrap(), edge_pairs(&loaded));
+    }
+
+    #[test]
+    fn non_finite_values_round_trip() {
+        let web = MorassWeb::make_random_web_seeded(3, 2, 1);
+        web.inject_node(1, f64::INFINITY).unwrap();
+        web.inject_node(2, f64::NEG_INFINITY).unwrap();
+        web.node_temp_charges[2].write().unwrap().clone_from(&f64::NAN);
+        let path = temp_path("non_finite");
+        web.save_to_json(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let loaded = MorassWeb::load_from_json(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(saved.contains("\"charge\":\"inf\"") && saved.contains("\"charge\":\"-inf\""));
+        assert!(saved.contains("\"NaN\""));
+        let charges: Vec<f64> = loaded.snapshot_nodes().iter().map(|n| n.charge).collect();
+        assert_eq!(charges[..2], [f64::INFINITY, f64::NEG_INFINITY]);
+        assert!(loaded.node_temp_charges[2].read().unwrap().is_nan());
+    }
+
+    #[test]
+    fn load_rejects_bare_infinity() {
+        let web = MorassWeb::make_random_web_seeded(2, 1, 1);
+        let path = temp_path("bare_infinity");
+        web.save_to_json(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::write(&path, saved.replacen("\"resting_potential\":0", "\"resting_potential\":inf", 1)).unwrap();
+        let loaded = MorassWeb::load_from_json(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.err().map(|e| e.kind()), Some(ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn loaded_edges_share_nodes() {
+        let web = MorassWeb::make_random_web_seeded(5, 6, 2);
+        let path = temp_path("shared_nodes");
+        web.save_to_json(&path).unwrap();
+        let loaded = MorassWeb::load_from_json(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        for edge in &loaded.edges {
+            let edge = edge.read().unwrap();
+            let start = edge.start_node.read().unwrap().id;
+            assert!(Arc::ptr_eq(&edge.start_node, &loaded.nodes[start - 1]));
+        }
+    }
+
+    #[test]
+    fn load_rejects_duplicate_edges() {
+        let web = MorassWeb::make_random_web_seeded(5, 3, 3);
+        let path = temp_path("duplicate_edges");
+        web.save_to_json(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        // Repeat the first edge in the edges array
+        let start = saved.find("{\"start_node\"").unwrap();
+        let end = start + saved[start..].find('}').unwrap() + 1;
+        let duplicated = format!("{}{},{}", &saved[..start], &saved[start..end], &saved[start..]);
+        fs::write(&path, duplicated).unwrap();
+        let loaded = MorassWeb::load_from_json(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.err().map(|e| e.kind()), Some(ErrorKind::InvalidData));
+    }
+}